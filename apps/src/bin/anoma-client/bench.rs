@@ -0,0 +1,559 @@
+//! A synthetic benchmarking harness for tx/VP WASM execution.
+//!
+//! Seeds a temporary storage backend with a configurable number of
+//! synthetic key/value entries, then repeatedly runs a selected tx or VP
+//! WASM module (e.g. `tx_read_storage_key`, `vp_read_storage_key`,
+//! `tx_memory_limit`, `tx_fuel_limit`) against it, reporting wall-clock
+//! time, gas consumed, remaining fuel and peak memory growth per run
+//! (see [`peak_rss_kib`]). Intended to catch regressions in storage-read
+//! and allocation-heavy paths across releases.
+
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use borsh::BorshSerialize;
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use namada::ledger::gas::BlockGasMeter;
+use namada::ledger::storage::mockdb::MockDB;
+use namada::ledger::storage::traits::Sha256Hasher;
+use namada::ledger::storage::write_log::WriteLog;
+use namada::ledger::storage::Storage;
+use namada::types::address;
+use namada::types::storage::Key;
+use namada::types::token;
+use thiserror::Error;
+
+use crate::fuel::{FuelCostTable, FuelMeter, FueledResult, COST_TABLE_V1};
+use crate::runtime_backend::RuntimeBackend;
+
+/// Which kind of WASM module is under test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleKind {
+    Tx,
+    Vp,
+}
+
+/// How to build the `tx_data` passed to the module under test.
+///
+/// Fixtures in this chunk don't agree on what `tx_data` means:
+/// `tx_read_storage_key`/`vp_read_storage_key` deserialize it as a
+/// `storage::Key` to read, `tx_memory_limit`/`vp_memory_limit`
+/// deserialize it as a raw `usize` allocation size, and `vp_eval`
+/// deserialize it as an [`EvalVp`]. Hardcoding one convention for every
+/// module under test would panic the other families on deserialization,
+/// so the kind is selected per benchmark run rather than inferred from
+/// `config.kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxDataKind {
+    /// Borsh-encoded `storage::Key` of the first seeded entry, as consumed
+    /// by `tx_read_storage_key` / `vp_read_storage_key`.
+    SeededKey,
+    /// Borsh-encoded `usize` equal to `config.value_size`, as consumed by
+    /// `tx_memory_limit` / `vp_memory_limit`.
+    AllocationSize,
+    /// Borsh-encoded [`EvalVp`], as consumed by `vp_eval`. `vp_code` is
+    /// read from `config.eval_vp_path`, `input` is the first seeded
+    /// entry's key.
+    EvalVp,
+}
+
+impl Default for TxDataKind {
+    fn default() -> Self {
+        Self::SeededKey
+    }
+}
+
+/// Mirrors `namada_vp_prelude::validity_predicate::EvalVp`'s field layout
+/// (see `wasm_for_tests/wasm_source/src/lib.rs`'s `vp_eval` fixture), so
+/// that borsh-encoding this struct produces `tx_data` the fixture can
+/// actually deserialize. That crate is a WASM-guest-only prelude the host
+/// side has no business depending on, so the shape is duplicated here
+/// rather than imported.
+#[derive(Debug, Clone, BorshSerialize)]
+struct EvalVp {
+    /// Compiled WASM bytecode of the VP to `eval`.
+    vp_code: Vec<u8>,
+    /// `tx_data` handed to the `eval`'d VP.
+    input: Vec<u8>,
+}
+
+/// Parameters for a single benchmark run.
+#[derive(Debug, Clone)]
+pub struct BenchConfig {
+    /// Kind of the module pointed to by `wasm_path`.
+    pub kind: ModuleKind,
+    /// Path to the compiled tx/VP WASM module under test.
+    pub wasm_path: PathBuf,
+    /// Number of synthetic key/value entries to seed into storage.
+    pub num_entries: usize,
+    /// Size in bytes of each synthetic value. Also doubles as the
+    /// allocation size passed to `tx_data` when `tx_data_kind` is
+    /// [`TxDataKind::AllocationSize`], following the `usize` tx_data
+    /// convention used by the `tx_memory_limit` fixture.
+    pub value_size: usize,
+    /// How to build `tx_data` for the module under test; see
+    /// [`TxDataKind`].
+    pub tx_data_kind: TxDataKind,
+    /// Path to the WASM module `eval`'d as `EvalVp::vp_code` when
+    /// `tx_data_kind` is [`TxDataKind::EvalVp`]. Unused otherwise.
+    pub eval_vp_path: PathBuf,
+    /// Number of untimed warm-up iterations run before measuring.
+    pub warmup_iterations: usize,
+    /// Number of measured iterations to average over.
+    pub iterations: usize,
+    /// Which WASM engine runs the module under test.
+    pub backend: RuntimeBackend,
+    /// Fuel budget charged against `fuel_cost_table` for each invocation.
+    /// A module whose total gas fee exceeds this once it returns (e.g. an
+    /// oversized `tx_memory_limit` allocation) is rejected as a metering
+    /// failure rather than left to skew the wall-clock measurement. A
+    /// module that never returns (e.g. `tx_fuel_limit`'s unbounded loop)
+    /// cannot be caught this way, since the charge only lands post-hoc —
+    /// see `execution_timeout` for what actually bounds that case.
+    pub fuel_budget: u64,
+    /// Versioned fuel cost table; see [`crate::fuel`].
+    pub fuel_cost_table: FuelCostTable,
+    /// Wall-clock bound on a single invocation. Independent of
+    /// `fuel_budget`: the fuel budget is charged from the total gas a run
+    /// reports once it returns (see [`run_blocking`]), so it can never
+    /// fire against a module that never returns. This timeout is the
+    /// actual hard stop for that case — e.g. `tx_fuel_limit`'s unbounded
+    /// `loop { ctx.write(...) }` — turning a would-be-permanent hang into
+    /// a clean [`BenchError::TimedOut`].
+    pub execution_timeout: Duration,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        Self {
+            kind: ModuleKind::Tx,
+            wasm_path: PathBuf::new(),
+            num_entries: 0,
+            value_size: 0,
+            tx_data_kind: TxDataKind::default(),
+            eval_vp_path: PathBuf::new(),
+            warmup_iterations: 0,
+            iterations: 1,
+            backend: RuntimeBackend::default(),
+            fuel_budget: u64::MAX,
+            fuel_cost_table: COST_TABLE_V1,
+            execution_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Aggregated measurements for one benchmark run.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BenchReport {
+    /// Mean wall-clock time per invocation, over `iterations` runs.
+    pub mean_duration: Duration,
+    /// Gas consumed by the last invocation.
+    pub gas: u64,
+    /// Fuel remaining out of `config.fuel_budget` after charging the last
+    /// invocation's total gas fee in one lump sum (see
+    /// [`run_blocking`]); see [`crate::fuel::FueledResult`] for where this
+    /// is actually threaded through.
+    pub remaining_fuel: u64,
+    /// Growth in the process's peak resident set size (`getrusage`'s
+    /// `ru_maxrss`, in KiB) across the last invocation; see
+    /// [`peak_rss_kib`] for why this is a delta rather than an absolute
+    /// figure. `0` on a platform/call failure rather than a hard error,
+    /// since it is a secondary metric alongside `mean_duration`/`gas`.
+    pub peak_rss_delta_kib: u64,
+}
+
+/// Raised when a benchmarked invocation does not return within
+/// `BenchConfig::execution_timeout`.
+///
+/// This is the hard stop for runaway modules like `tx_fuel_limit` /
+/// `vp_fuel_limit`'s unbounded loops: `namada::vm::wasm::run::{tx,vp}`
+/// can't be interrupted mid-flight from this checkout (see
+/// [`run_blocking`]), so without a wall-clock bound those loops would
+/// hang the bench process forever instead of being rejected the way an
+/// oversized `tx_memory_limit` allocation already is. It's a coarser
+/// stand-in for the deterministic per-instruction accounting
+/// [`crate::fuel::FuelHook`] is meant to grow into once it can be wired
+/// into the interpreter's dispatch loop.
+#[derive(Debug, Error)]
+pub enum BenchError {
+    #[error(
+        "execution did not return within {timeout:?}; treated as a runaway \
+         module and rejected, rather than left to hang or OOM"
+    )]
+    TimedOut { timeout: Duration },
+    #[error(
+        "--num-entries must be > 0 when --tx-data is {tx_data_kind:?}, since \
+         it builds tx_data from the first seeded entry"
+    )]
+    NoSeededEntry { tx_data_kind: TxDataKind },
+}
+
+/// A throwaway storage backend that is created before a benchmark run and
+/// dropped once it completes.
+struct TempStorage {
+    storage: Storage<MockDB, Sha256Hasher>,
+    write_log: WriteLog,
+}
+
+impl TempStorage {
+    fn new() -> Self {
+        Self {
+            storage: Storage::<MockDB, Sha256Hasher>::mock(),
+            write_log: WriteLog::default(),
+        }
+    }
+
+    /// Commits `value` under `key` directly into the backing storage DB,
+    /// not just the write log, so that `read_pre`-based reads (e.g.
+    /// `vp_read_storage_key`'s `ctx.read_pre`) see it as already-committed
+    /// pre-tx state. Seeding only `write_log` would leave those reads
+    /// finding nothing, since `read_pre` reads committed storage rather
+    /// than the write log.
+    fn commit(&mut self, key: &Key, value: Vec<u8>) {
+        self.storage
+            .write(key, value)
+            .expect("committing synthetic bench state into storage must not fail");
+    }
+}
+
+/// Writes `config.num_entries` synthetic entries of `config.value_size`
+/// bytes each, so that storage-read benchmarks exercise a representative
+/// number of lookups. The first entry is a `token::balance_key` for a
+/// fixed synthetic token/owner pair, matching the balance-key-shaped
+/// reads that `token`-handling tx/VP fixtures (e.g. `tx_mint_tokens`) do
+/// against storage; the rest are arbitrary `storage::Key` entries under
+/// a `bench` prefix, so the entry count still scales with
+/// `num_entries`. Entries are committed straight into storage (see
+/// [`TempStorage::commit`]) so both `ctx.read` and `ctx.read_pre`-based
+/// fixtures find them.
+fn seed_synthetic_state(storage: &mut TempStorage, config: &BenchConfig) -> Vec<Key> {
+    let mut keys = Vec::with_capacity(config.num_entries);
+    if config.num_entries > 0 {
+        let key = token::balance_key(&address::xan(), &address::testing::established_address_1());
+        let value = vec![0xAB_u8; config.value_size];
+        storage.commit(&key, value);
+        keys.push(key);
+    }
+    for i in 1..config.num_entries {
+        let key = Key::parse(format!("bench/synthetic/{}", i))
+            .expect("synthetic bench key must parse");
+        let value = vec![0xAB_u8; config.value_size];
+        storage.commit(&key, value);
+        keys.push(key);
+    }
+    keys
+}
+
+/// Builds the `tx_data` passed to the module under test, the way
+/// `config.tx_data_kind` says it expects it; see [`TxDataKind`].
+///
+/// `keys` is the seeding order returned by [`seed_synthetic_state`];
+/// `SeededKey` and `EvalVp` both need its first entry, so both reject
+/// `config.num_entries == 0` up front with [`BenchError::NoSeededEntry`]
+/// rather than silently falling back to an empty/malformed `tx_data` that
+/// would only fail once handed to the WASM guest (e.g. panicking
+/// `storage::Key::try_from_slice` on an empty slice, or misreading a
+/// borsh `Key` encoding as a bogus `EvalVp` length prefix).
+fn build_tx_data(keys: &[Key], config: &BenchConfig) -> Result<Vec<u8>> {
+    match config.tx_data_kind {
+        TxDataKind::SeededKey => {
+            let key = keys.first().ok_or(BenchError::NoSeededEntry {
+                tx_data_kind: config.tx_data_kind,
+            })?;
+            Ok(key.try_to_vec().expect("key must serialize"))
+        }
+        TxDataKind::AllocationSize => Ok(config
+            .value_size
+            .try_to_vec()
+            .expect("allocation size must serialize")),
+        TxDataKind::EvalVp => {
+            let input = keys
+                .first()
+                .ok_or(BenchError::NoSeededEntry {
+                    tx_data_kind: config.tx_data_kind,
+                })?
+                .try_to_vec()
+                .expect("key must serialize");
+            let vp_code = std::fs::read(&config.eval_vp_path).map_err(|e| {
+                eyre!(
+                    "failed to read eval vp module {:?}: {}",
+                    config.eval_vp_path,
+                    e
+                )
+            })?;
+            Ok(EvalVp { vp_code, input }
+                .try_to_vec()
+                .expect("EvalVp must serialize"))
+        }
+    }
+}
+
+/// Runs `config.wasm_path` against a freshly seeded [`TempStorage`],
+/// `config.warmup_iterations` times without measuring, then
+/// `config.iterations` times while timing and gas-metering, returning the
+/// wall-clock time averaged over the measured runs together with the gas,
+/// remaining fuel and peak-RSS growth of the last one.
+pub fn run(config: &BenchConfig) -> Result<BenchReport> {
+    let wasm_code: Arc<[u8]> = std::fs::read(&config.wasm_path)
+        .map_err(|e| eyre!("failed to read wasm module {:?}: {}", config.wasm_path, e))?
+        .into();
+
+    let mut storage = TempStorage::new();
+    let keys = seed_synthetic_state(&mut storage, config);
+    let tx_data = build_tx_data(&keys, config)?;
+
+    for _ in 0..config.warmup_iterations {
+        let fuel_meter = FuelMeter::new(config.fuel_budget, config.fuel_cost_table);
+        let (_, _, storage_back) = execute_once(
+            config.kind,
+            config.backend,
+            wasm_code.clone(),
+            tx_data.clone(),
+            storage,
+            fuel_meter,
+            config.execution_timeout,
+        )?;
+        storage = storage_back;
+    }
+
+    let mut total = Duration::ZERO;
+    let mut gas = 0;
+    let mut remaining_fuel = 0;
+    let mut peak_rss_delta_kib = 0;
+    for _ in 0..config.iterations.max(1) {
+        let fuel_meter = FuelMeter::new(config.fuel_budget, config.fuel_cost_table);
+        let start = Instant::now();
+        let (result, mem, storage_back) = execute_once(
+            config.kind,
+            config.backend,
+            wasm_code.clone(),
+            tx_data.clone(),
+            storage,
+            fuel_meter,
+            config.execution_timeout,
+        )?;
+        total += start.elapsed();
+        gas = result.value;
+        remaining_fuel = result.remaining_fuel;
+        peak_rss_delta_kib = mem;
+        storage = storage_back;
+    }
+
+    Ok(BenchReport {
+        mean_duration: total / config.iterations.max(1) as u32,
+        gas,
+        remaining_fuel,
+        peak_rss_delta_kib,
+    })
+}
+
+/// Runs `wasm_code` once against `storage` on a dedicated thread, bounded
+/// by `timeout`, so that a module which never returns doesn't hang the
+/// whole bench process.
+///
+/// `namada::vm::wasm::run::{tx,vp}` (via [`run_blocking`]) can't be
+/// interrupted mid-flight from this checkout — there's no fuel hook in
+/// their signatures to charge against and bail out early — so a module
+/// like `tx_fuel_limit`'s `loop { ctx.write(...) }` blocks the thread it
+/// runs on forever. Running it on its own thread and bounding the wait
+/// with [`mpsc::Receiver::recv_timeout`] turns that into a clean
+/// [`BenchError::TimedOut`] instead of a process hang: on timeout the
+/// thread is intentionally left running rather than joined (Rust gives no
+/// safe way to abort a thread blocked inside arbitrary WASM execution),
+/// and `storage`/`fuel_meter` are not recovered, so the caller must treat
+/// this as fatal for the whole benchmark run rather than continuing to
+/// the next iteration.
+fn execute_once(
+    kind: ModuleKind,
+    backend: RuntimeBackend,
+    wasm_code: Arc<[u8]>,
+    tx_data: Vec<u8>,
+    mut storage: TempStorage,
+    mut fuel_meter: FuelMeter,
+    timeout: Duration,
+) -> Result<(FueledResult<u64>, u64, TempStorage)> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let result = run_blocking(kind, backend, &wasm_code, tx_data, &mut storage, &mut fuel_meter);
+        // Ignore a disconnected receiver: it means the caller already
+        // timed out and moved on without us.
+        let _ = tx.send((result, storage));
+    });
+
+    match rx.recv_timeout(timeout) {
+        Ok((Ok((result, peak_rss_delta_kib)), storage)) => Ok((result, peak_rss_delta_kib, storage)),
+        Ok((Err(e), _storage)) => Err(e),
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(BenchError::TimedOut { timeout }.into()),
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            Err(eyre!("bench execution thread panicked before reporting a result"))
+        }
+    }
+}
+
+/// Returns the process's peak resident set size so far, in KiB, via
+/// `getrusage(2)`'s `ru_maxrss`.
+///
+/// `ru_maxrss` is a high-water mark that never decreases for the life of
+/// the process, so it can't be read once and reported directly as "memory
+/// used by this invocation" — it would include every prior invocation's
+/// growth too. [`run_blocking`] instead reads this before and after the
+/// invocation under test and reports the delta, which isolates how much
+/// *this* invocation grew the high-water mark (approximately: pages freed
+/// and not yet returned to the OS by the global allocator between
+/// invocations mean this can undercount, never overcount). Returns `0` on
+/// a platform/syscall failure rather than erroring, since this is a
+/// secondary metric alongside wall-clock time and gas.
+fn peak_rss_kib() -> u64 {
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+    // SAFETY: `usage` is a valid, suitably-sized out-pointer for
+    // `getrusage` to write into; its contents are only read once the
+    // call has reported success.
+    let succeeded = unsafe { libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) == 0 };
+    if !succeeded {
+        return 0;
+    }
+    // SAFETY: `getrusage` returning 0 guarantees `usage` was fully
+    // written.
+    let ru_maxrss = unsafe { usage.assume_init() }.ru_maxrss;
+    // `ru_maxrss` is already KiB on Linux, bytes on macOS.
+    let kib = if cfg!(target_os = "macos") {
+        ru_maxrss / 1024
+    } else {
+        ru_maxrss
+    };
+    kib.max(0) as u64
+}
+
+/// Executes `wasm_code` once against `storage` on the selected `backend`,
+/// blocking until `namada::vm::wasm::run::{tx,vp}` returns. Always called
+/// from inside the spawned thread in [`execute_once`], which is what lets
+/// a runaway module be bounded by a wall-clock timeout instead of hanging
+/// the whole bench process. Charges `fuel_meter` with the run's total gas
+/// fee post-hoc, since `namada::vm::wasm::run::{tx,vp}` only report one
+/// once the run returns (see [`crate::fuel`] for why). Returns the gas
+/// consumed paired with the fuel remaining afterwards, and the peak-RSS
+/// growth measured around the call (see [`peak_rss_kib`]).
+fn run_blocking(
+    kind: ModuleKind,
+    backend: RuntimeBackend,
+    wasm_code: &[u8],
+    tx_data: Vec<u8>,
+    storage: &mut TempStorage,
+    fuel_meter: &mut FuelMeter,
+) -> Result<(FueledResult<u64>, u64)> {
+    let mut gas_meter = BlockGasMeter::default();
+    let rss_before_kib = peak_rss_kib();
+    match kind {
+        ModuleKind::Tx => crate::runtime_backend::run_tx(
+            backend,
+            &mut storage.storage,
+            &mut storage.write_log,
+            &mut gas_meter,
+            wasm_code,
+            tx_data,
+        )
+        .wrap_err("tx execution failed")?,
+        ModuleKind::Vp => crate::runtime_backend::run_vp(
+            backend,
+            &storage.storage,
+            &storage.write_log,
+            &mut gas_meter,
+            wasm_code,
+            tx_data,
+        )
+        .wrap_err("vp execution failed")?,
+    };
+    let peak_rss_delta_kib = peak_rss_kib().saturating_sub(rss_before_kib);
+    let gas = gas_meter.get_current_transaction_fee();
+    fuel_meter
+        .charge(gas)
+        .map_err(|e| eyre!("bench run rejected by fuel meter: {}", e))?;
+    Ok((
+        FueledResult {
+            value: gas,
+            remaining_fuel: fuel_meter.remaining(),
+        },
+        peak_rss_delta_kib,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config(num_entries: usize, tx_data_kind: TxDataKind) -> BenchConfig {
+        BenchConfig {
+            num_entries,
+            value_size: 4,
+            tx_data_kind,
+            ..BenchConfig::default()
+        }
+    }
+
+    #[test]
+    fn seeds_exactly_num_entries_keys() {
+        let config = test_config(5, TxDataKind::SeededKey);
+        let mut storage = TempStorage::new();
+        let keys = seed_synthetic_state(&mut storage, &config);
+        assert_eq!(keys.len(), 5);
+    }
+
+    #[test]
+    fn first_seeded_key_is_the_token_balance_key() {
+        let config = test_config(3, TxDataKind::SeededKey);
+        let mut storage = TempStorage::new();
+        let keys = seed_synthetic_state(&mut storage, &config);
+        let expected =
+            token::balance_key(&address::xan(), &address::testing::established_address_1());
+        assert_eq!(keys[0], expected);
+    }
+
+    #[test]
+    fn no_entries_seeds_no_keys() {
+        let config = test_config(0, TxDataKind::SeededKey);
+        let mut storage = TempStorage::new();
+        let keys = seed_synthetic_state(&mut storage, &config);
+        assert!(keys.is_empty());
+    }
+
+    #[test]
+    fn seeded_key_tx_data_serializes_the_first_key() {
+        let config = test_config(2, TxDataKind::SeededKey);
+        let mut storage = TempStorage::new();
+        let keys = seed_synthetic_state(&mut storage, &config);
+        let tx_data = build_tx_data(&keys, &config).unwrap();
+        assert_eq!(tx_data, keys[0].try_to_vec().unwrap());
+    }
+
+    #[test]
+    fn allocation_size_tx_data_serializes_value_size() {
+        let config = test_config(0, TxDataKind::AllocationSize);
+        let tx_data = build_tx_data(&[], &config).unwrap();
+        assert_eq!(tx_data, config.value_size.try_to_vec().unwrap());
+    }
+
+    #[test]
+    fn seeded_key_with_no_entries_is_rejected_up_front() {
+        // Regression test: this combination used to silently fall back to
+        // an empty tx_data, which would only fail once handed to the WASM
+        // guest (e.g. a panic deep inside `tx_read_storage_key`) instead of
+        // a clean config error here.
+        let config = test_config(0, TxDataKind::SeededKey);
+        let err = build_tx_data(&[], &config).unwrap_err();
+        assert!(err
+            .downcast_ref::<BenchError>()
+            .map_or(false, |e| matches!(e, BenchError::NoSeededEntry { .. })));
+    }
+
+    #[test]
+    fn eval_vp_with_no_entries_is_rejected_up_front() {
+        let config = test_config(0, TxDataKind::EvalVp);
+        let err = build_tx_data(&[], &config).unwrap_err();
+        assert!(err
+            .downcast_ref::<BenchError>()
+            .map_or(false, |e| matches!(e, BenchError::NoSeededEntry { .. })));
+    }
+}
@@ -0,0 +1,93 @@
+//! Command dispatch for the `anoma-client` binary.
+//!
+//! NOTE: this module only exists in this chunk's checkout to carry the
+//! `bench` subsystem (see [`crate::bench`]). The real `anoma-client`'s
+//! wallet/tx/intent dispatch table is not part of this checkout, so it
+//! cannot be merged with here — the only subcommand this file knows
+//! about is `bench`. Wiring `bench` into the real `cli::main` alongside
+//! the rest of the client's command surface is follow-up work that
+//! belongs wherever that dispatch table actually lives.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use color_eyre::eyre::{eyre, Result};
+
+use crate::bench::{self, BenchConfig, ModuleKind, TxDataKind};
+use crate::runtime_backend::RuntimeBackend;
+
+/// Parses `std::env::args()` and dispatches to the matching subcommand.
+///
+/// Only `bench` is implemented here; see the module note above.
+pub async fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        Some("bench") => run_bench(args),
+        Some(other) => Err(eyre!(
+            "{:?} is not available in this checkout's cli::main, which only \
+             wires up `bench` (see the module docs on crate::cli)",
+            other
+        )),
+        None => Err(eyre!("expected a subcommand, e.g. `anoma-client bench`")),
+    }
+}
+
+/// Parses the flags for `anoma-client bench` and runs it, printing the
+/// resulting [`bench::BenchReport`].
+fn run_bench(mut args: impl Iterator<Item = String>) -> Result<()> {
+    let mut config = BenchConfig::default();
+    let mut backend = None;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| eyre!("{} expects a value", arg))
+        };
+        match arg.as_str() {
+            "--kind" => {
+                config.kind = match value()?.as_str() {
+                    "tx" => ModuleKind::Tx,
+                    "vp" => ModuleKind::Vp,
+                    other => return Err(eyre!("unknown --kind {:?}, expected tx or vp", other)),
+                }
+            }
+            "--wasm-path" => config.wasm_path = PathBuf::from(value()?),
+            "--num-entries" => config.num_entries = value()?.parse()?,
+            "--value-size" => config.value_size = value()?.parse()?,
+            "--tx-data" => {
+                config.tx_data_kind = match value()?.as_str() {
+                    "seeded-key" => TxDataKind::SeededKey,
+                    "allocation-size" => TxDataKind::AllocationSize,
+                    "eval-vp" => TxDataKind::EvalVp,
+                    other => {
+                        return Err(eyre!(
+                            "unknown --tx-data {:?}, expected seeded-key, \
+                             allocation-size or eval-vp",
+                            other
+                        ))
+                    }
+                }
+            }
+            "--eval-vp-path" => config.eval_vp_path = PathBuf::from(value()?),
+            "--warmup-iterations" => config.warmup_iterations = value()?.parse()?,
+            "--iterations" => config.iterations = value()?.parse()?,
+            "--wasm-runtime" => {
+                backend = Some(RuntimeBackend::from_str(&value()?).map_err(|e| eyre!(e))?)
+            }
+            "--fuel-budget" => config.fuel_budget = value()?.parse()?,
+            "--execution-timeout-secs" => {
+                config.execution_timeout = std::time::Duration::from_secs(value()?.parse()?)
+            }
+            other => return Err(eyre!("unknown flag {:?}", other)),
+        }
+    }
+    // `--wasm-runtime` wins when given explicitly; otherwise fall back to
+    // `NAMADA_WASM_RUNTIME`, defaulting to the interpreter if neither is set.
+    config.backend = backend.unwrap_or_else(RuntimeBackend::from_env);
+
+    let report = bench::run(&config)?;
+    println!(
+        "mean: {:?}, gas: {}, remaining fuel: {}, peak RSS delta: {} KiB",
+        report.mean_duration, report.gas, report.remaining_fuel, report.peak_rss_delta_kib
+    );
+    Ok(())
+}
@@ -1,4 +1,7 @@
+mod bench;
 mod cli;
+mod fuel;
+mod runtime_backend;
 
 use namada_apps::logging;
 use color_eyre::eyre::Result;
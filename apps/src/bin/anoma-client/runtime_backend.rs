@@ -0,0 +1,142 @@
+//! Selectable WASM execution backend flag for tx/VP execution.
+//!
+//! chunk0-2 asked for a second, wasmtime-backed execution engine,
+//! selectable at runtime, exposing the same `ctx.read`/`ctx.write`/
+//! `ctx.eval`/`log_string` host-function surface as the existing
+//! interpreter, so the VP/tx fixture set could be run against either
+//! backend to compare gas accounting and memory-limit enforcement.
+//!
+//! There's also no debug-assertions build mode here, for the same reason:
+//! that would only be worth having once there's a second backend to run
+//! it under.
+//!
+//! None of that is deliverable from this checkout: there is no `wasmtime`
+//! dependency available to build against, and the host-function surface
+//! (`ctx.read` et al.) is defined in `namada_tx_prelude`/
+//! `namada_vp_prelude`, neither of which are part of this chunk. A
+//! backend enum that accepts `"wasmtime"` and then always fails at run
+//! time would look like a feature while actually being a stub that can
+//! never succeed, so [`RuntimeBackend`] only offers
+//! [`RuntimeBackend::Interpreter`] for now; anything else given as
+//! `--wasm-runtime` is rejected at parse time instead of being accepted
+//! and failing later. An unrecognised `NAMADA_WASM_RUNTIME` value is not
+//! rejected: [`RuntimeBackend::from_env`] ignores it and uses the
+//! interpreter. **This request is closed as descoped in
+//! this checkout, not delivered**: a real wasmtime engine, the debug-
+//! assertions test mode, and the cross-backend divergence run are all
+//! follow-up work for whoever has the full `namada` checkout and the
+//! `wasmtime` dependency needed to build them.
+
+use std::env;
+use std::str::FromStr;
+
+/// Which WASM engine executes a tx/VP module.
+///
+/// Only the existing interpreter is implemented in this checkout; see
+/// the module docs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RuntimeBackend {
+    /// The existing interpreter-backed `namada::vm::wasm::run`.
+    #[default]
+    Interpreter,
+}
+
+impl FromStr for RuntimeBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "interpreter" => Ok(Self::Interpreter),
+            other => Err(format!(
+                "unknown wasm runtime backend {:?}: only \"interpreter\" is implemented in \
+                 this checkout (a wasmtime backend is not yet available; see the module docs \
+                 on crate::runtime_backend)",
+                other
+            )),
+        }
+    }
+}
+
+impl RuntimeBackend {
+    /// Reads the backend selection from `NAMADA_WASM_RUNTIME`, falling
+    /// back to [`RuntimeBackend::Interpreter`] if it is unset or does not
+    /// name an implemented backend.
+    pub fn from_env() -> Self {
+        env::var("NAMADA_WASM_RUNTIME")
+            .ok()
+            .and_then(|value| RuntimeBackend::from_str(&value).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Runs `wasm_code` as a transaction against `storage`/`write_log` using
+/// the given `backend`. Fuel is not charged here: `namada::vm::wasm::run`
+/// is an unmodified external function that only knows how to report a
+/// gas fee once a run completes, so the caller (`bench::run_blocking`)
+/// charges that fee against a [`crate::fuel::FuelMeter`] post-hoc instead
+/// of this function threading one through live.
+pub fn run_tx(
+    backend: RuntimeBackend,
+    storage: &mut namada::ledger::storage::Storage<
+        namada::ledger::storage::mockdb::MockDB,
+        namada::ledger::storage::traits::Sha256Hasher,
+    >,
+    write_log: &mut namada::ledger::storage::write_log::WriteLog,
+    gas_meter: &mut namada::ledger::gas::BlockGasMeter,
+    wasm_code: &[u8],
+    tx_data: Vec<u8>,
+) -> color_eyre::eyre::Result<()> {
+    match backend {
+        RuntimeBackend::Interpreter => namada::vm::wasm::run::tx(
+            storage, write_log, gas_meter, wasm_code, tx_data,
+        )
+        .map_err(|e| color_eyre::eyre::eyre!("{}", e)),
+    }
+}
+
+/// Runs `wasm_code` as a validity predicate against `storage`/`write_log`
+/// using the given `backend`. See [`run_tx`] for why fuel isn't charged
+/// here.
+pub fn run_vp(
+    backend: RuntimeBackend,
+    storage: &namada::ledger::storage::Storage<
+        namada::ledger::storage::mockdb::MockDB,
+        namada::ledger::storage::traits::Sha256Hasher,
+    >,
+    write_log: &namada::ledger::storage::write_log::WriteLog,
+    gas_meter: &mut namada::ledger::gas::BlockGasMeter,
+    wasm_code: &[u8],
+    tx_data: Vec<u8>,
+) -> color_eyre::eyre::Result<()> {
+    match backend {
+        RuntimeBackend::Interpreter => namada::vm::wasm::run::vp(
+            storage, write_log, gas_meter, wasm_code, tx_data,
+        )
+        .map_err(|e| color_eyre::eyre::eyre!("{}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str_accepts_interpreter() {
+        assert_eq!(
+            RuntimeBackend::from_str("interpreter").unwrap(),
+            RuntimeBackend::Interpreter
+        );
+    }
+
+    #[test]
+    fn from_str_rejects_unimplemented_backends() {
+        assert!(RuntimeBackend::from_str("wasmtime").is_err());
+        assert!(RuntimeBackend::from_str("jit").is_err());
+    }
+
+    #[test]
+    fn from_env_defaults_to_interpreter_when_unset() {
+        std::env::remove_var("NAMADA_WASM_RUNTIME");
+        assert_eq!(RuntimeBackend::from_env(), RuntimeBackend::Interpreter);
+    }
+}
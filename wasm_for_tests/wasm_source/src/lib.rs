@@ -25,6 +25,22 @@ pub mod main {
     }
 }
 
+/// A tx that runs an unbounded loop, writing to storage on every iteration; never returns.
+#[cfg(feature = "tx_fuel_limit")]
+pub mod main {
+    use namada_tx_prelude::*;
+
+    #[transaction]
+    fn apply_tx(ctx: &mut Ctx, tx_data: Vec<u8>) -> TxResult {
+        let key = storage::Key::try_from_slice(&tx_data[..]).unwrap();
+        let mut counter: u64 = 0;
+        loop {
+            ctx.write(&key, counter)?;
+            counter += 1;
+        }
+    }
+}
+
 /// A tx to be used as proposal_code
 #[cfg(feature = "tx_proposal_code")]
 pub mod main {
@@ -233,6 +249,26 @@ pub mod main {
     }
 }
 
+/// A VP that runs an unbounded loop, reading from storage on every iteration; never returns.
+#[cfg(feature = "vp_fuel_limit")]
+pub mod main {
+    use namada_vp_prelude::*;
+
+    #[validity_predicate]
+    fn validate_tx(
+        ctx: &Ctx,
+        tx_data: Vec<u8>,
+        _addr: Address,
+        _keys_changed: BTreeSet<storage::Key>,
+        _verifiers: BTreeSet<Address>,
+    ) -> VpResult {
+        let key = storage::Key::try_from_slice(&tx_data[..]).unwrap();
+        loop {
+            let _result: Option<Vec<u8>> = ctx.read_pre(&key)?;
+        }
+    }
+}
+
 /// A VP that attempts to read the given key from storage (state prior to tx
 /// execution). Returns `true`, if the allocation is within memory limits.
 #[cfg(feature = "vp_read_storage_key")]